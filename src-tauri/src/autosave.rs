@@ -0,0 +1,228 @@
+// Debounced autosave with crash-recovery snapshots.
+//
+// As the user edits a project the frontend calls `mark_dirty` with the latest
+// buffer. Rather than hit the disk on every keystroke, updates are funnelled
+// through an `mpsc` channel to a single background thread that coalesces rapid
+// edits and only writes a `<path>.recovery` sidecar once editing has been idle
+// for a configurable interval. On startup a newer recovery file than the saved
+// project signals that the last session ended without saving.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Default idle interval before a recovery snapshot is written.
+const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// A pending edit handed to the debounce thread, stamped with the generation
+/// that was current when it was queued.
+struct Snapshot {
+    path: PathBuf,
+    contents: String,
+    generation: u64,
+}
+
+/// Managed autosave state: the channel into the debounce thread plus the path
+/// of the project currently being edited.
+///
+/// `generation` is bumped by `clear_recovery` (an explicit save) and re-checked
+/// by the writer under `write_lock` immediately before it writes, so a debounce
+/// write that was already mid-flight can never resurrect a snapshot over the
+/// just-saved file — the check and the delete are serialised by the same lock.
+pub struct Autosave {
+    sender: Sender<Snapshot>,
+    path: Mutex<Option<PathBuf>>,
+    generation: Arc<AtomicU64>,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEBOUNCE)
+    }
+}
+
+impl Autosave {
+    /// Spawn the debounce thread and return the state handle.
+    pub fn new(debounce: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel::<Snapshot>();
+        let generation = Arc::new(AtomicU64::new(0));
+        let write_lock = Arc::new(Mutex::new(()));
+
+        let writer_generation = Arc::clone(&generation);
+        let writer_lock = Arc::clone(&write_lock);
+        thread::spawn(move || {
+            // Block for the first edit, then keep coalescing newer edits until
+            // the channel has been quiet for `debounce`; the last buffer seen
+            // wins and is the only one written.
+            while let Ok(mut latest) = receiver.recv() {
+                loop {
+                    match receiver.recv_timeout(debounce) {
+                        Ok(newer) => latest = newer,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                // Re-check the generation under the write lock: if a save has
+                // cleared the recovery file since this buffer was queued, the
+                // buffer is stale and must not be written back.
+                if let Ok(_guard) = writer_lock.lock() {
+                    if latest.generation == writer_generation.load(Ordering::SeqCst) {
+                        let _ = std::fs::write(recovery_path(&latest.path), &latest.contents);
+                    }
+                }
+            }
+        });
+
+        Self {
+            sender,
+            path: Mutex::new(None),
+            generation,
+            write_lock,
+        }
+    }
+
+    /// Remember the path of the project currently open, so `mark_dirty` knows
+    /// where to place recovery snapshots.
+    pub fn set_path(&self, path: impl Into<PathBuf>) {
+        if let Ok(mut current) = self.path.lock() {
+            *current = Some(path.into());
+        }
+    }
+
+    /// Remove the recovery sidecar for the current project, if any. Bumping the
+    /// generation and deleting under `write_lock` makes the cancel authoritative:
+    /// a debounce write still in flight re-checks the generation under the same
+    /// lock and declines to re-create the file we just deleted.
+    pub fn clear_recovery(&self) {
+        if let Ok(_guard) = self.write_lock.lock() {
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            if let Ok(current) = self.path.lock() {
+                if let Some(path) = current.as_ref() {
+                    let _ = std::fs::remove_file(recovery_path(path));
+                }
+            }
+        }
+    }
+
+    /// Queue the latest buffer for the open project, stamped with the current
+    /// generation so a later save can invalidate it. Backs [`mark_dirty`].
+    pub fn enqueue(&self, contents: String) -> Result<(), String> {
+        let path = self
+            .path
+            .lock()
+            .map_err(|_| "autosave state was poisoned".to_string())?
+            .clone()
+            .ok_or_else(|| "no project is open to autosave".to_string())?;
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.sender
+            .send(Snapshot {
+                path,
+                contents,
+                generation,
+            })
+            .map_err(|_| "autosave worker is not running".to_string())
+    }
+}
+
+#[tauri::command]
+pub fn mark_dirty(
+    autosave: tauri::State<'_, std::sync::Arc<Autosave>>,
+    contents: String,
+) -> Result<(), String> {
+    autosave.enqueue(contents)
+}
+
+/// The recovery sidecar path for a project file, e.g. `demo.macronade` ->
+/// `demo.macronade.recovery`.
+pub fn recovery_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".recovery");
+    PathBuf::from(name)
+}
+
+/// Whether a recovery snapshot for `path` exists and is newer than the saved
+/// project (or the project no longer exists).
+pub fn recovery_is_newer(path: &Path) -> bool {
+    let recovery = recovery_path(path);
+    let recovery_mtime = match std::fs::metadata(&recovery).and_then(|m| m.modified()) {
+        Ok(mtime) => mtime,
+        Err(_) => return false,
+    };
+    match std::fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(project_mtime) => recovery_mtime > project_mtime,
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use filetime::{set_file_mtime, FileTime};
+
+    fn temp_project(name: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("macronade-autosave-{}-{}", std::process::id(), n));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn recovery_is_newer_compares_mtimes() {
+        let project = temp_project("demo.macronade");
+        std::fs::write(&project, "saved").unwrap();
+        let recovery = recovery_path(&project);
+        std::fs::write(&recovery, "unsaved").unwrap();
+
+        // Recovery older than the project: nothing to recover.
+        set_file_mtime(&project, FileTime::from_unix_time(2_000, 0)).unwrap();
+        set_file_mtime(&recovery, FileTime::from_unix_time(1_000, 0)).unwrap();
+        assert!(!recovery_is_newer(&project));
+
+        // Recovery newer than the project: a crash left unsaved edits.
+        set_file_mtime(&recovery, FileTime::from_unix_time(3_000, 0)).unwrap();
+        assert!(recovery_is_newer(&project));
+
+        // No recovery file at all.
+        std::fs::remove_file(&recovery).unwrap();
+        assert!(!recovery_is_newer(&project));
+    }
+
+    #[test]
+    fn debounce_coalesces_to_the_last_buffer() {
+        let project = temp_project("coalesce.macronade");
+        std::fs::write(&project, "saved").unwrap();
+        let autosave = Autosave::new(Duration::from_millis(50));
+        autosave.set_path(&project);
+
+        autosave.enqueue("first".into()).unwrap();
+        autosave.enqueue("second".into()).unwrap();
+        autosave.enqueue("third".into()).unwrap();
+
+        thread::sleep(Duration::from_millis(200));
+        let written = std::fs::read_to_string(recovery_path(&project)).unwrap();
+        assert_eq!(written, "third");
+    }
+
+    #[test]
+    fn save_within_debounce_window_suppresses_pending_write() {
+        let project = temp_project("race.macronade");
+        std::fs::write(&project, "saved").unwrap();
+        let autosave = Autosave::new(Duration::from_millis(100));
+        autosave.set_path(&project);
+
+        // Edit queued, then an explicit save clears recovery before the timer
+        // fires; the pending buffer must not resurrect the file.
+        autosave.enqueue("stale".into()).unwrap();
+        autosave.clear_recovery();
+
+        thread::sleep(Duration::from_millis(250));
+        assert!(!recovery_path(&project).exists());
+    }
+}
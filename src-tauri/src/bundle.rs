@@ -0,0 +1,306 @@
+// ZIP-based project bundle format.
+//
+// A bundle is a ZIP archive holding `project.json` (the project manifest) plus
+// every asset the project references — icons, sound clips, and so on — so a
+// project can be shared or archived as a single file without losing its assets.
+// Each entry's last-modified time is carried through save and restored on load
+// so round-tripping a bundle doesn't needlessly rewrite timestamps. Note that
+// ZIP stores times as DOS date-time with 2-second granularity, so an asset with
+// an odd-second mtime is rounded down to the even second below on round-trip; exact
+// sub-2s preservation would require recording the mtime in `project.json`.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use filetime::{set_file_mtime, FileTime};
+use serde::Serialize;
+use time::OffsetDateTime;
+use zip::write::FileOptions;
+use zip::{DateTime, ZipArchive, ZipWriter};
+
+/// The manifest entry name inside a bundle.
+const MANIFEST_NAME: &str = "project.json";
+
+/// A loaded bundle: the manifest JSON plus the names of the extracted assets.
+#[derive(Debug, Serialize)]
+pub struct Bundle {
+    pub manifest: String,
+    pub assets: Vec<String>,
+}
+
+#[tauri::command]
+pub fn save_project_bundle(
+    path: String,
+    manifest_json: String,
+    assets: Vec<String>,
+) -> Result<(), String> {
+    let file = File::create(&path).map_err(stringify)?;
+    let mut zip = ZipWriter::new(file);
+
+    zip.start_file(MANIFEST_NAME, FileOptions::default())
+        .map_err(stringify)?;
+    zip.write_all(manifest_json.as_bytes()).map_err(stringify)?;
+
+    for asset in &assets {
+        // Namespace each asset under `assets/` by its original relative path so
+        // two references sharing a basename (`a/icon.png`, `b/icon.png`) don't
+        // collide and silently overwrite each other in the archive.
+        let name = archive_name(asset)?;
+        let options = FileOptions::default().last_modified_time(mtime_of(asset)?);
+        zip.start_file(&name, options).map_err(stringify)?;
+
+        let mut source = File::open(asset).map_err(stringify)?;
+        let mut buffer = Vec::new();
+        source.read_to_end(&mut buffer).map_err(stringify)?;
+        zip.write_all(&buffer).map_err(stringify)?;
+    }
+
+    zip.finish().map_err(stringify)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn load_project_bundle(path: String) -> Result<Bundle, String> {
+    let file = File::open(&path).map_err(stringify)?;
+    let mut archive = ZipArchive::new(file).map_err(stringify)?;
+
+    let mut manifest = String::new();
+    let mut assets = Vec::new();
+
+    // Extract asset entries next to the bundle itself, restoring each entry's
+    // recorded modification time.
+    let parent = Path::new(&path).parent().map(Path::to_path_buf);
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(stringify)?;
+
+        // Reject traversal (`../`) and absolute entry names so a crafted bundle
+        // can't write outside the extraction directory (zip-slip). We only ever
+        // write safe relative `assets/...` names in `save_project_bundle`, so a
+        // sanitized name is all we expect to see here.
+        let name = match entry.enclosed_name() {
+            Some(name) => name,
+            None => return Err(format!("bundle entry has an unsafe path: {}", entry.name())),
+        };
+
+        if name == Path::new(MANIFEST_NAME) {
+            entry.read_to_string(&mut manifest).map_err(stringify)?;
+            continue;
+        }
+
+        let name_str = name
+            .to_str()
+            .ok_or_else(|| "bundle entry name is not valid UTF-8".to_string())?
+            .to_string();
+
+        let target = match &parent {
+            Some(dir) => dir.join(&name),
+            None => name.clone(),
+        };
+
+        // Never clobber a file that's already there — a bundle is untrusted
+        // input and shouldn't be able to overwrite the user's other files.
+        if target.exists() {
+            return Err(format!(
+                "refusing to overwrite existing file: {}",
+                target.display()
+            ));
+        }
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir).map_err(stringify)?;
+        }
+
+        let mut out = File::create(&target).map_err(stringify)?;
+        std::io::copy(&mut entry, &mut out).map_err(stringify)?;
+        restore_mtime(&target, entry.last_modified())?;
+
+        assets.push(name_str);
+    }
+
+    Ok(Bundle { manifest, assets })
+}
+
+/// Read the manifest out of a bundle without extracting its assets.
+///
+/// Used by `load_project` to transparently open bundles saved by
+/// [`save_project_bundle`].
+pub fn read_manifest(path: &str) -> Result<String, String> {
+    let file = File::open(path).map_err(stringify)?;
+    let mut archive = ZipArchive::new(file).map_err(stringify)?;
+    let mut entry = archive.by_name(MANIFEST_NAME).map_err(stringify)?;
+    let mut manifest = String::new();
+    entry.read_to_string(&mut manifest).map_err(stringify)?;
+    Ok(manifest)
+}
+
+/// Whether `path` points at a ZIP bundle, detected by its magic number.
+pub fn is_bundle(path: &str) -> bool {
+    match File::open(path) {
+        Ok(mut file) => {
+            let mut magic = [0u8; 4];
+            file.read_exact(&mut magic).is_ok() && magic == [0x50, 0x4b, 0x03, 0x04]
+        }
+        Err(_) => false,
+    }
+}
+
+/// The ZIP last-modified time of a file on disk.
+fn mtime_of(path: &str) -> Result<DateTime, String> {
+    let modified = std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .map_err(stringify)?;
+    let offset = OffsetDateTime::from(modified);
+    DateTime::try_from(offset).map_err(|_| "asset modification time is out of range".into())
+}
+
+/// Restore an extracted file's modification time from its ZIP entry.
+fn restore_mtime(path: &Path, recorded: DateTime) -> Result<(), String> {
+    let offset = OffsetDateTime::try_from(recorded)
+        .map_err(|_| "bundled modification time is out of range".to_string())?;
+    let ft = FileTime::from_unix_time(offset.unix_timestamp(), offset.nanosecond());
+    set_file_mtime(path, ft).map_err(stringify)
+}
+
+/// The archive entry name for an asset: its path's normal components joined
+/// under an `assets/` prefix. Traversal (`..`), absolute roots, and drive
+/// prefixes are dropped so the stored name is always a safe relative path that
+/// still distinguishes assets sharing a basename.
+fn archive_name(path: &str) -> Result<String, String> {
+    let rel: PathBuf = Path::new(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect();
+    let rel = rel
+        .to_str()
+        .ok_or_else(|| format!("asset path is not valid UTF-8: {path}"))?;
+    if rel.is_empty() {
+        return Err(format!("asset path has no file name: {path}"));
+    }
+    Ok(format!("assets/{rel}"))
+}
+
+fn stringify(error: impl std::fmt::Display) -> String {
+    error.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A unique scratch directory for a single test, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+            let dir = std::env::temp_dir().join(format!(
+                "macronade-bundle-{}-{}",
+                std::process::id(),
+                n
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn archive_name_namespaces_and_keeps_collisions_distinct() {
+        assert_eq!(archive_name("a/icon.png").unwrap(), "assets/a/icon.png");
+        assert_ne!(
+            archive_name("a/icon.png").unwrap(),
+            archive_name("b/icon.png").unwrap()
+        );
+        // Traversal and absolute roots are stripped to a safe relative path.
+        assert_eq!(archive_name("/etc/passwd").unwrap(), "assets/etc/passwd");
+        assert_eq!(archive_name("../../secret").unwrap(), "assets/secret");
+    }
+
+    #[test]
+    fn bundle_round_trips_manifest_and_assets() {
+        let dir = TempDir::new();
+        let asset = dir.path("icon.png");
+        std::fs::write(&asset, b"pixels").unwrap();
+        // Pin an even-second mtime so the 2s-granularity DOS time round-trips.
+        filetime::set_file_mtime(&asset, FileTime::from_unix_time(1_600_000_000, 0)).unwrap();
+
+        let bundle = dir.path("project.macronade");
+        save_project_bundle(
+            bundle.to_str().unwrap().to_string(),
+            "{\"name\":\"demo\"}".to_string(),
+            vec![asset.to_str().unwrap().to_string()],
+        )
+        .unwrap();
+
+        assert!(is_bundle(bundle.to_str().unwrap()));
+
+        let loaded = load_project_bundle(bundle.to_str().unwrap().to_string()).unwrap();
+        assert_eq!(loaded.manifest, "{\"name\":\"demo\"}");
+        assert_eq!(loaded.assets, vec!["assets/icon.png".to_string()]);
+
+        let extracted = dir.path("assets/icon.png");
+        assert_eq!(std::fs::read(&extracted).unwrap(), b"pixels");
+        let mtime = FileTime::from_last_modification_time(&std::fs::metadata(&extracted).unwrap());
+        assert_eq!(mtime.unix_seconds(), 1_600_000_000);
+    }
+
+    #[test]
+    fn load_rejects_zip_slip_entries() {
+        let dir = TempDir::new();
+        let bundle = dir.path("evil.macronade");
+
+        // Hand-craft an archive with a traversal entry name.
+        {
+            let file = File::create(&bundle).unwrap();
+            let mut zip = ZipWriter::new(file);
+            zip.start_file(MANIFEST_NAME, FileOptions::default()).unwrap();
+            zip.write_all(b"{}").unwrap();
+            zip.start_file("../escaped.txt", FileOptions::default()).unwrap();
+            zip.write_all(b"pwned").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let result = load_project_bundle(bundle.to_str().unwrap().to_string());
+        assert!(result.is_err());
+        assert!(!dir.path("../escaped.txt").exists());
+    }
+
+    #[test]
+    fn load_refuses_to_overwrite_existing_file() {
+        let dir = TempDir::new();
+        let asset = dir.path("icon.png");
+        std::fs::write(&asset, b"pixels").unwrap();
+
+        let bundle = dir.path("project.macronade");
+        save_project_bundle(
+            bundle.to_str().unwrap().to_string(),
+            "{}".to_string(),
+            vec![asset.to_str().unwrap().to_string()],
+        )
+        .unwrap();
+
+        // Pre-create the extraction target so the load must refuse.
+        let target = dir.path(&archive_name(asset.to_str().unwrap()).unwrap());
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::write(&target, b"existing").unwrap();
+
+        let result = load_project_bundle(bundle.to_str().unwrap().to_string());
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&target).unwrap(), b"existing");
+    }
+}
@@ -1,12 +1,85 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+use tauri_plugin_cli::CliExt;
+
+mod autosave;
+mod bundle;
+mod macros;
+
+use autosave::Autosave;
+use macros::Recorder;
+
+/// A project that was opened from the command line before the window existed.
+///
+/// `.setup()` runs during `build()`, before the webview has navigated or
+/// registered any `listen()` handlers, and Tauri doesn't buffer events for late
+/// subscribers — so a CLI-launched project is stashed here and handed to the
+/// frontend when it invokes [`take_pending_project`] on startup, rather than
+/// emitted into the void.
+#[derive(Default)]
+struct PendingProject(Mutex<Option<PendingOpen>>);
+
+/// The outcome of a CLI-launched open: the project's contents or the error that
+/// stopped it opening, and whether a newer recovery snapshot is available.
+#[derive(Clone, Serialize)]
+struct PendingOpen {
+    path: String,
+    contents: Option<String>,
+    error: Option<String>,
+    recovery_available: bool,
+}
+
+#[tauri::command]
+fn save_project(
+    autosave: tauri::State<'_, Arc<Autosave>>,
+    path: String,
+    contents: String,
+) -> Result<(), String> {
+    std::fs::write(&path, contents).map_err(|error| error.to_string())?;
+    // The saved file is now authoritative; drop any stale recovery snapshot.
+    autosave.set_path(&path);
+    autosave.clear_recovery();
+    Ok(())
+}
+
 #[tauri::command]
-fn save_project(path: String, contents: String) -> Result<(), String> {
-    std::fs::write(path, contents).map_err(|error| error.to_string())
+fn load_project(
+    app: tauri::AppHandle,
+    autosave: tauri::State<'_, Arc<Autosave>>,
+    path: String,
+) -> Result<String, String> {
+    autosave.set_path(&path);
+    let contents = read_project(&path)?;
+
+    // A recovery snapshot newer than the saved project means the previous
+    // session crashed with unsaved edits, so offer to restore it. A project
+    // opened from the command line reports this through
+    // `PendingOpen::recovery_available` instead.
+    if autosave::recovery_is_newer(std::path::Path::new(&path)) {
+        let _ = app.emit("recovery-available", &path);
+    }
+
+    Ok(contents)
 }
 
+/// Read a project's manifest, transparently opening ZIP bundles written by
+/// `save_project_bundle` and falling back to the legacy raw-string format.
+fn read_project(path: &str) -> Result<String, String> {
+    if bundle::is_bundle(path) {
+        bundle::read_manifest(path)
+    } else {
+        std::fs::read_to_string(path).map_err(|error| error.to_string())
+    }
+}
+
+/// Hand the frontend any project opened from the command line before the window
+/// was ready. Returns `None` on a normal launch. Clears the slot once taken.
 #[tauri::command]
-fn load_project(path: String) -> Result<String, String> {
-    std::fs::read_to_string(path).map_err(|error| error.to_string())
+fn take_pending_project(pending: tauri::State<'_, PendingProject>) -> Option<PendingOpen> {
+    pending.0.lock().ok().and_then(|mut slot| slot.take())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -14,7 +87,57 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![save_project, load_project])
+        .plugin(tauri_plugin_cli::init())
+        .manage(Arc::new(Recorder::default()))
+        .manage(Arc::new(Autosave::default()))
+        .manage(PendingProject::default())
+        .setup(|app| {
+            // Let users open a project by passing its path on the command line
+            // (e.g. double-clicking a `.macronade` file). `.setup()` runs before
+            // the webview can `listen()`, so stash the result and let the frontend
+            // pull it with `take_pending_project` once it's ready.
+            if let Some(path) = cli_project_path(app) {
+                let autosave = app.state::<Arc<Autosave>>();
+                autosave.set_path(&path);
+                let pending = match read_project(&path) {
+                    Ok(contents) => PendingOpen {
+                        recovery_available: autosave::recovery_is_newer(
+                            std::path::Path::new(&path),
+                        ),
+                        path,
+                        contents: Some(contents),
+                        error: None,
+                    },
+                    Err(error) => PendingOpen {
+                        error: Some(format!("could not open {path}: {error}")),
+                        path,
+                        contents: None,
+                        recovery_available: false,
+                    },
+                };
+                *app.state::<PendingProject>().0.lock().unwrap() = Some(pending);
+            }
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            save_project,
+            load_project,
+            take_pending_project,
+            bundle::save_project_bundle,
+            bundle::load_project_bundle,
+            autosave::mark_dirty,
+            macros::start_recording,
+            macros::stop_recording,
+            macros::play_macro,
+            macros::cancel_playback,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Pull the positional `path` argument out of the CLI matches, if one was given.
+fn cli_project_path(app: &tauri::App) -> Option<String> {
+    let matches = app.cli().matches().ok()?;
+    let value = matches.args.get("path")?.value.as_str()?;
+    Some(value.to_string())
+}
@@ -0,0 +1,313 @@
+// Global input macro recording and playback, backed by the `rdev` crate.
+//
+// Recording spawns a background `rdev::listen` thread that captures raw input
+// events together with a monotonic timestamp; consecutive timestamps are turned
+// into inter-event delays so playback can reproduce the original pacing. Mouse
+// coordinates are stored as a fraction of the screen so a macro recorded on one
+// display resolution replays sensibly on another.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rdev::{listen, simulate, EventType, Key};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+/// A single recorded input event plus the delay to wait before replaying it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub event: EventType,
+    pub delay_ms: u64,
+}
+
+/// Payload for the `macro-progress` event emitted after every replayed step.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProgressPayload {
+    pub current_step: usize,
+    pub total_steps: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Shared recorder state managed by Tauri.
+///
+/// `recording` and `playing` live behind their own `AtomicBool`s so the
+/// `rdev::listen` callback can consult them without contending for the `steps`
+/// mutex on every event.
+pub struct Recorder {
+    steps: Mutex<Vec<MacroStep>>,
+    last_event: Mutex<Option<Instant>>,
+    held_keys: Mutex<Vec<Key>>,
+    recording: Arc<AtomicBool>,
+    playing: Arc<AtomicBool>,
+    /// Set by `cancel_playback` to stop an in-flight `play_macro` between steps.
+    cancel: Arc<AtomicBool>,
+    /// Whether the resident `rdev::listen` thread has already been spawned.
+    /// `rdev::listen` blocks for the life of its thread and has no portable
+    /// stop, so we spawn it once and gate capture with `recording` rather than
+    /// leaking a fresh listener (and double-capturing) on every record session.
+    listening: Arc<AtomicBool>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            steps: Mutex::new(Vec::new()),
+            last_event: Mutex::new(None),
+            held_keys: Mutex::new(Vec::new()),
+            recording: Arc::new(AtomicBool::new(false)),
+            playing: Arc::new(AtomicBool::new(false)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            listening: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// The modifier keys we make sure to release when recording stops, so the OS is
+/// never left with a stuck modifier after a recording that ended mid-chord.
+fn is_modifier(key: &Key) -> bool {
+    matches!(
+        key,
+        Key::ShiftLeft
+            | Key::ShiftRight
+            | Key::ControlLeft
+            | Key::ControlRight
+            | Key::Alt
+            | Key::AltGr
+            | Key::MetaLeft
+            | Key::MetaRight
+    )
+}
+
+/// Scale an absolute mouse position down to a 0.0..=1.0 fraction of the screen.
+fn normalize(x: f64, y: f64) -> (f64, f64) {
+    match rdev::display_size() {
+        Ok((w, h)) if w > 0 && h > 0 => fraction_of(x, y, w, h),
+        _ => (x, y),
+    }
+}
+
+/// Map a stored fraction back onto the current screen for playback.
+fn denormalize(x: f64, y: f64) -> (f64, f64) {
+    match rdev::display_size() {
+        Ok((w, h)) if w > 0 && h > 0 => scale_to(x, y, w, h),
+        _ => (x, y),
+    }
+}
+
+/// Express an absolute position as a fraction of the given display size.
+fn fraction_of(x: f64, y: f64, w: u64, h: u64) -> (f64, f64) {
+    (x / w as f64, y / h as f64)
+}
+
+/// Map a fraction back onto an absolute position for the given display size.
+fn scale_to(x: f64, y: f64, w: u64, h: u64) -> (f64, f64) {
+    (x * w as f64, y * h as f64)
+}
+
+#[tauri::command]
+pub fn start_recording(recorder: tauri::State<'_, Arc<Recorder>>) -> Result<(), String> {
+    if recorder.recording.swap(true, Ordering::SeqCst) {
+        return Err("a recording is already in progress".into());
+    }
+
+    *recorder.steps.lock().map_err(lock_poisoned)? = Vec::new();
+    *recorder.last_event.lock().map_err(lock_poisoned)? = None;
+    recorder.held_keys.lock().map_err(lock_poisoned)?.clear();
+
+    // Spawn the listener at most once for the life of the process. A second
+    // `start_recording` simply re-arms the `recording` flag the resident thread
+    // already consults, so there is never more than one listener capturing (and
+    // thus no duplicated steps across record sessions).
+    if recorder.listening.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let recorder = Arc::clone(&recorder);
+    let recording = Arc::clone(&recorder.recording);
+    let playing = Arc::clone(&recorder.playing);
+    // Flags to clear once `listen` returns. On success it blocks forever, but if
+    // it fails immediately (no macOS accessibility permission, Wayland, headless)
+    // we must release `listening`/`recording` so the recorder isn't left a silent
+    // permanent dead end — the next `start_recording` then re-spawns and retries.
+    let listening = Arc::clone(&recorder.listening);
+    let recording_reset = Arc::clone(&recorder.recording);
+    thread::spawn(move || {
+        // `rdev::listen` blocks for the lifetime of the thread; the `recording`
+        // flag gates whether each event is actually captured, and `playing`
+        // drops our own simulated events so playback is never re-recorded.
+        let result = listen(move |event| {
+            if !recording.load(Ordering::SeqCst) || playing.load(Ordering::SeqCst) {
+                return;
+            }
+
+            let now = Instant::now();
+            let delay_ms = {
+                let mut last = match recorder.last_event.lock() {
+                    Ok(last) => last,
+                    Err(_) => return,
+                };
+                let delay = last.map(|t| now.duration_since(t).as_millis() as u64);
+                *last = Some(now);
+                delay.unwrap_or(0)
+            };
+
+            let event = match event.event_type {
+                EventType::MouseMove { x, y } => {
+                    let (x, y) = normalize(x, y);
+                    EventType::MouseMove { x, y }
+                }
+                other => other,
+            };
+
+            if let EventType::KeyPress(key) = event {
+                if is_modifier(&key) {
+                    if let Ok(mut held) = recorder.held_keys.lock() {
+                        if !held.contains(&key) {
+                            held.push(key);
+                        }
+                    }
+                }
+            }
+            if let EventType::KeyRelease(key) = event {
+                if let Ok(mut held) = recorder.held_keys.lock() {
+                    held.retain(|k| k != &key);
+                }
+            }
+
+            if let Ok(mut steps) = recorder.steps.lock() {
+                steps.push(MacroStep { event, delay_ms });
+            }
+        });
+
+        // `listen` only returns on error; make the failure visible and retryable.
+        if result.is_err() {
+            recording_reset.store(false, Ordering::SeqCst);
+            listening.store(false, Ordering::SeqCst);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_recording(recorder: tauri::State<'_, Arc<Recorder>>) -> Result<Vec<MacroStep>, String> {
+    recorder.recording.store(false, Ordering::SeqCst);
+
+    // Release any modifiers still held down when recording stopped so the OS
+    // isn't left with, say, a stuck Shift key.
+    let held: Vec<Key> = std::mem::take(&mut *recorder.held_keys.lock().map_err(lock_poisoned)?);
+    for key in held {
+        let _ = simulate(&EventType::KeyRelease(key));
+    }
+
+    let steps = recorder.steps.lock().map_err(lock_poisoned)?.clone();
+    Ok(steps)
+}
+
+#[tauri::command]
+pub fn play_macro(
+    app: AppHandle,
+    recorder: tauri::State<'_, Arc<Recorder>>,
+    steps: Vec<MacroStep>,
+) -> Result<(), String> {
+    if recorder.playing.swap(true, Ordering::SeqCst) {
+        return Err("a macro is already playing".into());
+    }
+    recorder.cancel.store(false, Ordering::SeqCst);
+
+    // Run playback on its own thread so the command returns immediately and the
+    // frontend can drive a live progress bar off the emitted events — and, in
+    // particular, invoke `cancel_playback` while a long macro is still running.
+    let playing = Arc::clone(&recorder.playing);
+    let cancel = Arc::clone(&recorder.cancel);
+    thread::spawn(move || {
+        let _ = replay(&app, &steps, &cancel);
+        playing.store(false, Ordering::SeqCst);
+    });
+
+    Ok(())
+}
+
+/// Request that an in-flight [`play_macro`] stop at the next step boundary.
+#[tauri::command]
+pub fn cancel_playback(recorder: tauri::State<'_, Arc<Recorder>>) {
+    recorder.cancel.store(true, Ordering::SeqCst);
+}
+
+/// Replay each step, sleeping its recorded delay and translating normalized
+/// mouse coordinates back onto the current display. Progress is streamed to the
+/// frontend over the general event channel: `macro-started` up front, a
+/// `macro-progress` payload after each step, and `macro-finished` /
+/// `macro-aborted` when replay ends. The `cancel` flag is consulted before each
+/// step so a frontend cancel button stops playback without polling.
+fn replay(app: &AppHandle, steps: &[MacroStep], cancel: &AtomicBool) -> Result<(), String> {
+    let total_steps = steps.len();
+    let started = Instant::now();
+    let _ = app.emit("macro-started", total_steps);
+
+    for (index, step) in steps.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            let _ = app.emit("macro-aborted", "cancelled");
+            return Ok(());
+        }
+
+        thread::sleep(Duration::from_millis(step.delay_ms));
+
+        let event = match step.event {
+            EventType::MouseMove { x, y } => {
+                let (x, y) = denormalize(x, y);
+                EventType::MouseMove { x, y }
+            }
+            other => other,
+        };
+
+        if let Err(error) = simulate(&event) {
+            let _ = app.emit("macro-aborted", format!("{error:?}"));
+            return Err(format!("failed to simulate event: {error:?}"));
+        }
+
+        let _ = app.emit(
+            "macro-progress",
+            ProgressPayload {
+                current_step: index + 1,
+                total_steps,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            },
+        );
+    }
+
+    let _ = app.emit("macro-finished", total_steps);
+    Ok(())
+}
+
+fn lock_poisoned<T>(_: std::sync::PoisonError<T>) -> String {
+    "recorder state was poisoned".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates_round_trip_across_resolutions() {
+        // A click recorded at the centre of a 1920x1080 display should land at
+        // the centre of a 2560x1440 display after normalize -> denormalize.
+        let (fx, fy) = fraction_of(960.0, 540.0, 1920, 1080);
+        assert!((fx - 0.5).abs() < f64::EPSILON);
+        assert!((fy - 0.5).abs() < f64::EPSILON);
+
+        let (x, y) = scale_to(fx, fy, 2560, 1440);
+        assert!((x - 1280.0).abs() < 1e-9);
+        assert!((y - 720.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn same_resolution_is_identity() {
+        let (fx, fy) = fraction_of(123.0, 456.0, 1920, 1080);
+        let (x, y) = scale_to(fx, fy, 1920, 1080);
+        assert!((x - 123.0).abs() < 1e-9);
+        assert!((y - 456.0).abs() < 1e-9);
+    }
+}